@@ -2,86 +2,412 @@ use clap::{arg, command};
 use futures::stream::{self, StreamExt};
 use regex::Regex;
 use serde::Serialize;
-use std::io::{Read, Write};
-use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::net::SocketAddr;
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream as AsyncTcpStream;
 use tokio::time::timeout;
 
-#[derive(Serialize)]
+mod output;
+mod probes;
+mod targets;
+mod timing;
+mod tls;
+mod unix;
+
+#[derive(Serialize, Clone)]
 struct ScanResult {
     target: String,
-    port: u16,
+    /// `None` for Unix domain socket targets, which have no port number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
     status: String,
     service: Option<String>,
     version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tls: Option<tls::TlsInfo>,
 }
 
-async fn scan_port(target: SocketAddr, timeout_ms: u64) -> Option<ScanResult> {
-    let timeout_duration = Duration::from_millis(timeout_ms);
+fn service_probe_for(port: u16) -> Vec<u8> {
+    match port {
+        21 => b"QUIT\r\n".to_vec(),
+        22 => b"SSH-2.0-OpenSSH_7.4p1 Debian-10+deb9u7\r\n".to_vec(),
+        25 => b"HELO example.com\r\n".to_vec(),
+        53 => b"\x00\x00\x10\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec(),
+        80 | 8080 => b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec(),
+        110 => b"QUIT\r\n".to_vec(),
+        143 => b"a1 LOGOUT\r\n".to_vec(),
+        443 | 8443 => b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec(),
+        465 => b"QUIT\r\n".to_vec(),
+        993 => b"a1 LOGOUT\r\n".to_vec(),
+        995 => b"QUIT\r\n".to_vec(),
+        1723 => b"\x00\x00\x00\x00\x00\x00\x00\x00".to_vec(),
+        3306 => b"\x0a\x00\x00\x01\x85\xa6\x03\x00\x00\x00\x00\x01\x08\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec(),
+        3389 => b"\x03\x00\x00\x13\x0e\xe0\x00\x00\x00\x00\x00\x01\x00\x08\x00\x03\x00\x00\x00".to_vec(),
+        5432 => b"\x00\x00\x00\x08\x04\xd2\x16\x2f".to_vec(),
+        5900 | 5901 => b"RFB 003.008\n".to_vec(),
+        6379 => b"PING\r\n".to_vec(),
+        _ => Vec::new(),
+    }
+}
 
+fn detect_service(port: u16, response: &str) -> (String, Option<String>) {
+    match port {
+        21 => ftp_service_detection(response),
+        22 => ssh_service_detection(response),
+        25 => smtp_service_detection(response),
+        53 => dns_service_detection(response),
+        80 | 8080 => http_service_detection(response),
+        110 => pop3_service_detection(response),
+        143 => imap_service_detection(response),
+        443 | 8443 => https_service_detection(response),
+        465 => smtps_service_detection(response),
+        993 => imaps_service_detection(response),
+        995 => pop3s_service_detection(response),
+        1723 => pptp_service_detection(response),
+        3306 => mysql_service_detection(response),
+        3389 => rdp_service_detection(response),
+        5432 => postgres_service_detection(response),
+        5900 | 5901 => vnc_service_detection(response),
+        6379 => redis_service_detection(response),
+        _ => ("Unknown".to_string(), None),
+    }
+}
+
+/// Gates `scan_port_attempt` behind the adaptive controller's window. RTT is
+/// recorded per individual connect inside `scan_port_attempt` (via
+/// `timed_connect`) rather than once here, since a probe-db detection can
+/// make several sequential reconnects before returning.
+async fn scan_port(
+    target: SocketAddr,
+    hostname: Option<std::sync::Arc<str>>,
+    controller: std::sync::Arc<timing::AdaptiveController>,
+    tls_ports: std::sync::Arc<Vec<u16>>,
+    probe_db: std::sync::Arc<Option<probes::ProbeDb>>,
+) -> Option<ScanResult> {
+    let _permit = controller.acquire().await;
+    let timeout_ms = controller.current_timeout_ms();
+
+    scan_port_attempt(target, hostname, timeout_ms, tls_ports, probe_db, &controller).await
+}
+
+/// Connects with the given timeout and immediately records the outcome (RTT
+/// on success, failure otherwise), so a caller that reconnects several times
+/// per port feeds the controller one accurate sample per connect instead of
+/// one inflated sample for the whole detection.
+async fn timed_connect(
+    target: SocketAddr,
+    timeout_duration: Duration,
+    controller: &timing::AdaptiveController,
+) -> Option<AsyncTcpStream> {
+    let start = Instant::now();
     match timeout(timeout_duration, AsyncTcpStream::connect(target)).await {
-        Ok(Ok(mut stream)) => {
-            let service_probe = match target.port() {
-                21 => b"QUIT\r\n".to_vec(),
-                22 => b"SSH-2.0-OpenSSH_7.4p1 Debian-10+deb9u7\r\n".to_vec(),
-                25 => b"HELO example.com\r\n".to_vec(),
-                53 => b"\x00\x00\x10\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec(),
-                80 | 8080 => b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec(),
-                110 => b"QUIT\r\n".to_vec(),
-                143 => b"a1 LOGOUT\r\n".to_vec(),
-                443 | 8443 => b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec(),
-                465 => b"QUIT\r\n".to_vec(),
-                993 => b"a1 LOGOUT\r\n".to_vec(),
-                995 => b"QUIT\r\n".to_vec(),
-                1723 => b"\x00\x00\x00\x00\x00\x00\x00\x00".to_vec(),
-                3306 => b"\x0a\x00\x00\x01\x85\xa6\x03\x00\x00\x00\x00\x01\x08\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec(),
-                3389 => b"\x03\x00\x00\x13\x0e\xe0\x00\x00\x00\x00\x00\x01\x00\x08\x00\x03\x00\x00\x00".to_vec(),
-                5432 => b"\x00\x00\x00\x08\x04\xd2\x16\x2f".to_vec(),
-                5900 | 5901 => b"RFB 003.008\n".to_vec(),
-                6379 => b"PING\r\n".to_vec(),
-                _ => Vec::new(),
-            };
+        Ok(Ok(stream)) => {
+            controller.record(true, Some(start.elapsed()));
+            Some(stream)
+        }
+        _ => {
+            controller.record(false, None);
+            None
+        }
+    }
+}
+
+async fn scan_port_attempt(
+    target: SocketAddr,
+    hostname: Option<std::sync::Arc<str>>,
+    timeout_ms: u64,
+    tls_ports: std::sync::Arc<Vec<u16>>,
+    probe_db: std::sync::Arc<Option<probes::ProbeDb>>,
+    controller: &timing::AdaptiveController,
+) -> Option<ScanResult> {
+    let timeout_duration = Duration::from_millis(timeout_ms);
+
+    if tls::is_tls_port(target.port(), &tls_ports) {
+        // Prefer the original hostname as SNI so vhosted/CDN-fronted targets
+        // return their real certificate instead of the default one for the IP.
+        let sni = hostname.as_deref().map(str::to_string).unwrap_or_else(|| target.ip().to_string());
+
+        if let Some(db) = probe_db.as_ref() {
+            if let Some(result) = scan_tls_with_probe_db(target, &sni, timeout_ms, db, controller).await {
+                return Some(result);
+            }
+            // Nothing ever completed a TLS handshake across the whole probe
+            // loop -- fall through to the plaintext probe-db path below in
+            // case the port isn't actually speaking TLS at all.
+        } else {
+            let raw = timed_connect(target, timeout_duration, controller).await?;
+            if let Some((mut tls_stream, tls_info)) = tls::handshake(raw, &sni, timeout_ms).await {
+                let service_probe = service_probe_for(target.port());
+                let response = tls::probe_banner(&mut tls_stream, &service_probe).await;
+                let (service, version) = detect_service(target.port(), &response);
+
+                return Some(ScanResult {
+                    target: target.ip().to_string(),
+                    port: Some(target.port()),
+                    status: "open".to_string(),
+                    service: Some(service),
+                    version,
+                    tls: Some(tls_info),
+                });
+            }
+            // TLS handshake failed even though the raw connect succeeded
+            // (timeout, protocol mismatch, or a non-TLS service sitting on a
+            // user-specified --tls-ports port). The port is still open, so
+            // fall through to the plaintext path below instead of reporting
+            // it closed.
+        }
+    }
 
-            let _ = stream.write(&service_probe).await;
-            let mut buffer = [0; 1024];
-            let _ = stream.read(&mut buffer).await;
-
-            let response = String::from_utf8_lossy(&buffer);
-            let (service, version) = match target.port() {
-                21 => ftp_service_detection(&response),
-                22 => ssh_service_detection(&response),
-                25 => smtp_service_detection(&response),
-                53 => dns_service_detection(&response),
-                80 | 8080 => http_service_detection(&response),
-                110 => pop3_service_detection(&response),
-                143 => imap_service_detection(&response),
-                443 | 8443 => https_service_detection(&response),
-                465 => smtps_service_detection(&response),
-                993 => imaps_service_detection(&response),
-                995 => pop3s_service_detection(&response),
-                1723 => pptp_service_detection(&response),
-                3306 => mysql_service_detection(&response),
-                3389 => rdp_service_detection(&response),
-                5432 => postgres_service_detection(&response),
-                5900 | 5901 => vnc_service_detection(&response),
-                6379 => redis_service_detection(&response),
-                _ => ("Unknown".to_string(), None),
+    if let Some(db) = probe_db.as_ref() {
+        return scan_port_with_probe_db(target, timeout_ms, db, controller).await;
+    }
+
+    let service_probe = service_probe_for(target.port());
+    let mut stream = timed_connect(target, timeout_duration, controller).await?;
+    let _ = stream.write(&service_probe).await;
+    let mut buffer = [0; 1024];
+    let _ = stream.read(&mut buffer).await;
+
+    let response = String::from_utf8_lossy(&buffer);
+    let (service, version) = detect_service(target.port(), &response);
+
+    Some(ScanResult {
+        target: target.ip().to_string(),
+        port: Some(target.port()),
+        status: "open".to_string(),
+        service: Some(service),
+        version,
+        tls: None,
+    })
+}
+
+/// Detects the service on `target` by running the nmap-style probe database
+/// against it: each candidate probe reconnects and sends its probestring,
+/// and the first hard `match` wins (falling back to the last `softmatch`).
+async fn scan_port_with_probe_db(
+    target: SocketAddr,
+    timeout_ms: u64,
+    db: &probes::ProbeDb,
+    controller: &timing::AdaptiveController,
+) -> Option<ScanResult> {
+    let connected = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let connected_flag = connected.clone();
+
+    let detection = db
+        .detect(target.port(), false, move |probe_bytes, wait_ms| {
+            let connected_flag = connected_flag.clone();
+            async move {
+                let wait = Duration::from_millis(wait_ms.unwrap_or(timeout_ms));
+                let Some(mut stream) = timed_connect(target, wait, controller).await else {
+                    return Vec::new();
+                };
+                connected_flag.store(true, std::sync::atomic::Ordering::Relaxed);
+
+                if !probe_bytes.is_empty() {
+                    let _ = stream.write_all(&probe_bytes).await;
+                }
+                let mut buffer = [0u8; 2048];
+                let n = timeout(wait, stream.read(&mut buffer))
+                    .await
+                    .ok()
+                    .and_then(|r| r.ok())
+                    .unwrap_or(0);
+                buffer[..n].to_vec()
+            }
+        })
+        .await;
+
+    match detection {
+        Some(d) => {
+            let version = match (d.product.or(d.version), d.info) {
+                (Some(pv), Some(info)) => Some(format!("{} ({})", pv, info)),
+                (Some(pv), None) => Some(pv),
+                (None, Some(info)) => Some(info),
+                (None, None) => None,
             };
+            Some(ScanResult {
+                target: target.ip().to_string(),
+                port: Some(target.port()),
+                status: "open".to_string(),
+                service: Some(d.service),
+                version,
+                tls: None,
+            })
+        }
+        None if connected.load(std::sync::atomic::Ordering::Relaxed) => Some(ScanResult {
+            target: target.ip().to_string(),
+            port: Some(target.port()),
+            status: "open".to_string(),
+            service: Some("Unknown".to_string()),
+            version: None,
+            tls: None,
+        }),
+        None => None,
+    }
+}
+
+/// Mirrors `scan_port_with_probe_db` for TLS-flagged ports: each candidate
+/// probe reconnects *and* redoes the TLS handshake, since nmap's `sslports`
+/// probes are meant to run over a live TLS session rather than plaintext.
+/// Passes `is_tls: true` so `sslports` (otherwise dead code) actually
+/// restricts which probes are tried.
+async fn scan_tls_with_probe_db(
+    target: SocketAddr,
+    sni: &str,
+    timeout_ms: u64,
+    db: &probes::ProbeDb,
+    controller: &timing::AdaptiveController,
+) -> Option<ScanResult> {
+    let connected = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let connected_flag = connected.clone();
+    let tls_info: std::sync::Arc<std::sync::Mutex<Option<tls::TlsInfo>>> =
+        std::sync::Arc::new(std::sync::Mutex::new(None));
+    let tls_info_slot = tls_info.clone();
+    let sni = sni.to_string();
+
+    let detection = db
+        .detect(target.port(), true, move |probe_bytes, wait_ms| {
+            let connected_flag = connected_flag.clone();
+            let tls_info_slot = tls_info_slot.clone();
+            let sni = sni.clone();
+            async move {
+                let wait = Duration::from_millis(wait_ms.unwrap_or(timeout_ms));
+                // One candidate probe's connect+handshake is the indivisible
+                // unit of "one attempt" here, so it's timed and recorded as a
+                // single RTT sample -- distinct from the original bug, which
+                // blended every candidate probe in the loop into one sample.
+                let start = Instant::now();
+                let Ok(Ok(raw)) = timeout(wait, AsyncTcpStream::connect(target)).await else {
+                    controller.record(false, None);
+                    return Vec::new();
+                };
+                let Some((mut tls_stream, info)) =
+                    tls::handshake(raw, &sni, wait.as_millis() as u64).await
+                else {
+                    controller.record(false, None);
+                    return Vec::new();
+                };
+                controller.record(true, Some(start.elapsed()));
+                connected_flag.store(true, std::sync::atomic::Ordering::Relaxed);
+                *tls_info_slot.lock().unwrap() = Some(info);
+
+                timeout(wait, tls::probe_banner(&mut tls_stream, &probe_bytes))
+                    .await
+                    .map(String::into_bytes)
+                    .unwrap_or_default()
+            }
+        })
+        .await;
 
+    let tls_info = tls_info.lock().unwrap().clone();
+
+    match detection {
+        Some(d) => {
+            let version = match (d.product.or(d.version), d.info) {
+                (Some(pv), Some(info)) => Some(format!("{} ({})", pv, info)),
+                (Some(pv), None) => Some(pv),
+                (None, Some(info)) => Some(info),
+                (None, None) => None,
+            };
             Some(ScanResult {
                 target: target.ip().to_string(),
-                port: target.port(),
+                port: Some(target.port()),
                 status: "open".to_string(),
-                service: Some(service),
+                service: Some(d.service),
                 version,
+                tls: tls_info,
             })
         }
-        _ => None,
+        None if connected.load(std::sync::atomic::Ordering::Relaxed) => Some(ScanResult {
+            target: target.ip().to_string(),
+            port: Some(target.port()),
+            status: "open".to_string(),
+            service: Some("Unknown".to_string()),
+            version: None,
+            tls: tls_info,
+        }),
+        None => None,
     }
 }
 
+/// Gates `scan_unix_target` behind the adaptive controller, mirroring `scan_port`.
+async fn scan_unix(
+    path: String,
+    controller: std::sync::Arc<timing::AdaptiveController>,
+    probe_db: std::sync::Arc<Option<probes::ProbeDb>>,
+) -> Option<ScanResult> {
+    let _permit = controller.acquire().await;
+    let timeout_ms = controller.current_timeout_ms();
+    let start = Instant::now();
+
+    let result = scan_unix_target(path, timeout_ms, probe_db).await;
+
+    let rtt = result.is_some().then(|| start.elapsed());
+    controller.record(result.is_some(), rtt);
+    result
+}
+
+/// Scans a `unix:/path` target: connects over a `UnixStream` and runs the
+/// same service/banner detection as a TCP port, just without a port number.
+/// Without a probe database, `detect_service` is keyed on the port, so a
+/// Unix socket (port 0) is always reported as "Unknown".
+async fn scan_unix_target(path: String, timeout_ms: u64, probe_db: std::sync::Arc<Option<probes::ProbeDb>>) -> Option<ScanResult> {
+    if let Some(db) = probe_db.as_ref() {
+        let connected = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let connected_flag = connected.clone();
+        let path_for_probe = path.clone();
+
+        let detection = db
+            .detect(0, false, move |probe_bytes, wait_ms| {
+                let connected_flag = connected_flag.clone();
+                let path = path_for_probe.clone();
+                async move {
+                    let wait = wait_ms.unwrap_or(timeout_ms);
+                    match unix::probe(&path, wait, &probe_bytes).await {
+                        Some(response) => {
+                            connected_flag.store(true, std::sync::atomic::Ordering::Relaxed);
+                            response.into_bytes()
+                        }
+                        None => Vec::new(),
+                    }
+                }
+            })
+            .await;
+
+        return match detection {
+            Some(d) => Some(ScanResult {
+                target: path,
+                port: None,
+                status: "open".to_string(),
+                service: Some(d.service),
+                version: d.version.or(d.product),
+                tls: None,
+            }),
+            None if connected.load(std::sync::atomic::Ordering::Relaxed) => Some(ScanResult {
+                target: path,
+                port: None,
+                status: "open".to_string(),
+                service: Some("Unknown".to_string()),
+                version: None,
+                tls: None,
+            }),
+            None => None,
+        };
+    }
+
+    let response = unix::probe(&path, timeout_ms, &[]).await?;
+    let (service, version) = detect_service(0, &response);
+    Some(ScanResult {
+        target: path,
+        port: None,
+        status: "open".to_string(),
+        service: Some(service),
+        version,
+        tls: None,
+    })
+}
+
 fn ftp_service_detection(response: &str) -> (String, Option<String>) {
     if response.contains("220") && response.contains("FTP") {
         let version = extract_version(response, r"[\d.]+");
@@ -152,7 +478,7 @@ fn imap_service_detection(response: &str) -> (String, Option<String>) {
 }
 
 fn https_service_detection(response: &str) -> (String, Option<String>) {
-    if response.contains("HTTP") && response.contains("SSL") {
+    if response.contains("HTTP") {
         if response.contains("Apache") {
             let version = extract_version(response, r"Apache/[\d.]+");
             ("Apache HTTPS".to_string(), version)
@@ -168,7 +494,7 @@ fn https_service_detection(response: &str) -> (String, Option<String>) {
 }
 
 fn smtps_service_detection(response: &str) -> (String, Option<String>) {
-    if response.contains("220") && response.contains("SMTPS") {
+    if response.contains("220") {
         let version = extract_version(response, r"[\d.]+");
         ("SMTPS".to_string(), version)
     } else {
@@ -177,7 +503,7 @@ fn smtps_service_detection(response: &str) -> (String, Option<String>) {
 }
 
 fn imaps_service_detection(response: &str) -> (String, Option<String>) {
-    if response.contains("* OK") && response.contains("IMAP") && response.contains("SSL") {
+    if response.contains("* OK") {
         let version = extract_version(response, r"IMAP\d+[\w.-]+");
         ("IMAPS".to_string(), version)
     } else {
@@ -186,7 +512,7 @@ fn imaps_service_detection(response: &str) -> (String, Option<String>) {
 }
 
 fn pop3s_service_detection(response: &str) -> (String, Option<String>) {
-    if response.contains("+OK") && response.contains("POP3") && response.contains("SSL") {
+    if response.contains("+OK") {
         let version = extract_version(response, r"[\d.]+");
         ("POP3S".to_string(), version)
     } else {
@@ -253,42 +579,130 @@ fn extract_version(response: &str, pattern: &str) -> Option<String> {
         .map(|v| v.as_str().to_string())
 }
 
-async fn scan_ports(target: IpAddr, start_port: u16, end_port: u16, num_threads: usize, timeout_ms: u64) -> Vec<ScanResult> {
+async fn scan_ports(
+    target_spec: &str,
+    start_port: u16,
+    end_port: u16,
+    timing_preset: timing::TimingPreset,
+    tls_ports: Vec<u16>,
+    probe_db: Option<probes::ProbeDb>,
+    result_senders: Vec<tokio::sync::mpsc::UnboundedSender<ScanResult>>,
+) -> Vec<ScanResult> {
     let ports: Vec<u16> = (start_port..=end_port).collect();
-    let total_ports = ports.len() as u16;
-
-    let results = stream::iter(ports)
-        .map(|port| SocketAddr::new(target, port))
-        .map(|addr| tokio::spawn(scan_port(addr, timeout_ms)))
-        .buffer_unordered(num_threads)
+    let tls_ports = std::sync::Arc::new(tls_ports);
+    let probe_db = std::sync::Arc::new(probe_db);
+    let max_window = timing_preset.max_window;
+    let controller = timing::AdaptiveController::new(timing_preset);
+
+    let addrs = targets::expand_targets(target_spec).await;
+
+    let results = addrs
+        .flat_map(move |resolved| match resolved {
+            targets::ResolvedTarget::Net { ip, hostname } => {
+                let hostname: Option<std::sync::Arc<str>> = hostname.map(std::sync::Arc::from);
+                stream::iter(ports.clone())
+                    .map({
+                        let controller = controller.clone();
+                        let tls_ports = tls_ports.clone();
+                        let probe_db = probe_db.clone();
+                        move |port| {
+                            let addr = SocketAddr::new(ip, port);
+                            tokio::spawn(scan_port(
+                                addr,
+                                hostname.clone(),
+                                controller.clone(),
+                                tls_ports.clone(),
+                                probe_db.clone(),
+                            ))
+                        }
+                    })
+                    .boxed()
+            }
+            targets::ResolvedTarget::Unix(path) => stream::iter(vec![path])
+                .map({
+                    let controller = controller.clone();
+                    let probe_db = probe_db.clone();
+                    move |path| tokio::spawn(scan_unix(path, controller.clone(), probe_db.clone()))
+                })
+                .boxed(),
+        })
+        .buffer_unordered(max_window)
         .filter_map(|res| async { res.unwrap_or(None) })
+        .inspect(|result| {
+            for sender in &result_senders {
+                let _ = sender.send(result.clone());
+            }
+        })
         .collect()
         .await;
 
-    println!("Scanned {} ports in total.", total_ports);
-
     results
 }
 
 fn print_results(results: &[ScanResult], format: &str) {
     match format {
+        "ndjson" => {
+            // Already streamed to stdout as each result was discovered.
+        }
         "json" => {
             let json = serde_json::to_string_pretty(results).unwrap();
             println!("{}", json);
         }
         "csv" => {
+            #[derive(Serialize)]
+            struct ScanResultRow<'a> {
+                target: &'a str,
+                port: Option<u16>,
+                status: &'a str,
+                service: &'a Option<String>,
+                version: &'a Option<String>,
+                tls_protocol: Option<&'a str>,
+                tls_cipher_suite: Option<&'a str>,
+                tls_alpn: Option<&'a str>,
+                tls_subject_cn: Option<&'a str>,
+                tls_sans: Option<String>,
+                tls_issuer: Option<&'a str>,
+                tls_not_before: Option<&'a str>,
+                tls_not_after: Option<&'a str>,
+            }
+
             let mut wtr = csv::Writer::from_writer(std::io::stdout());
             for result in results {
-                wtr.serialize(result).unwrap();
+                let row = ScanResultRow {
+                    target: &result.target,
+                    port: result.port,
+                    status: &result.status,
+                    service: &result.service,
+                    version: &result.version,
+                    tls_protocol: result.tls.as_ref().map(|t| t.protocol.as_str()),
+                    tls_cipher_suite: result.tls.as_ref().map(|t| t.cipher_suite.as_str()),
+                    tls_alpn: result.tls.as_ref().and_then(|t| t.alpn.as_deref()),
+                    tls_subject_cn: result.tls.as_ref().and_then(|t| t.subject_cn.as_deref()),
+                    tls_sans: result.tls.as_ref().map(|t| t.sans.join(";")),
+                    tls_issuer: result.tls.as_ref().and_then(|t| t.issuer.as_deref()),
+                    tls_not_before: result.tls.as_ref().map(|t| t.not_before.as_str()),
+                    tls_not_after: result.tls.as_ref().map(|t| t.not_after.as_str()),
+                };
+                wtr.serialize(row).unwrap();
             }
             wtr.flush().unwrap();
         }
         _ => {
             for result in results {
+                let port = result
+                    .port
+                    .map(|p| p.to_string())
+                    .unwrap_or_else(|| "-".to_string());
                 println!(
                     "Target: {}, Port: {}, Status: {}, Service: {:?}, Version: {:?}",
-                    result.target, result.port, result.status, result.service, result.version
+                    result.target, port, result.status, result.service, result.version
                 );
+                if let Some(tls) = &result.tls {
+                    println!(
+                        "  TLS: {} {} ALPN={:?} CN={:?} SANs={:?} Issuer={:?} NotBefore={} NotAfter={}",
+                        tls.protocol, tls.cipher_suite, tls.alpn, tls.subject_cn, tls.sans, tls.issuer, tls.not_before, tls.not_after
+                    );
+                }
             }
         }
     }
@@ -302,7 +716,7 @@ async fn main() {
         .author("hdunl")
         .about("A simple port scanner written in Rust")
         .arg(
-            arg!(-t --target <TARGET> "The target IP address to scan")
+            arg!(-t --target <TARGET> "Comma-separated targets: IPv4/IPv6 literals, CIDR blocks, or hostnames")
                 .required(true)
         )
         .arg(
@@ -314,24 +728,40 @@ async fn main() {
                 .default_value("1024")
         )
         .arg(
-            arg!(-j --threads <THREADS> "The number of threads to use for scanning")
+            arg!(-j --threads <THREADS> "The number of threads to use for scanning (ignored if --timing is set)")
                 .default_value("1000")
         )
         .arg(
-            arg!(-T --timeout <TIMEOUT> "The timeout duration in milliseconds")
+            arg!(-T --timeout <TIMEOUT> "The timeout duration in milliseconds (ignored if --timing is set)")
                 .default_value("750")
         )
         .arg(
-            arg!(-f --format <FORMAT> "The output format (text, json, csv)")
+            arg!(--timing <TEMPLATE> "nmap-style timing template 0 (paranoid) to 5 (insane); adapts window/timeout from observed RTT and loss")
+                .required(false)
+        )
+        .arg(
+            arg!(-f --format <FORMAT> "The output format (text, json, csv, ndjson)")
                 .default_value("text")
         )
+        .arg(
+            arg!(--"tls-ports" <PORTS> "Comma-separated extra ports to probe with a TLS handshake")
+                .default_value("")
+        )
+        .arg(
+            arg!(--"probe-db" <PATH> "Path to an nmap-style service-probes file for version detection")
+                .required(false)
+        )
+        .arg(
+            arg!(--redis <URL> "Redis connection URL to stream each open result to as it's found, e.g. redis://host:6379/0")
+                .required(false)
+        )
+        .arg(
+            arg!(--"redis-key" <KEY> "Redis list key that discovered results are RPUSHed onto")
+                .default_value("rpoke:results")
+        )
         .get_matches();
 
-    let target: IpAddr = matches
-        .get_one::<String>("target")
-        .unwrap()
-        .parse()
-        .expect("Invalid target IP address");
+    let target = matches.get_one::<String>("target").unwrap();
     let start_port = matches
         .get_one::<String>("start-port")
         .unwrap()
@@ -353,12 +783,51 @@ async fn main() {
         .parse()
         .expect("Invalid timeout");
     let format = matches.get_one::<String>("format").unwrap();
+    let tls_ports: Vec<u16> = matches
+        .get_one::<String>("tls-ports")
+        .unwrap()
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.trim().parse().expect("Invalid TLS port"))
+        .collect();
+    let probe_db = matches
+        .get_one::<String>("probe-db")
+        .map(|path| probes::ProbeDb::load(path).expect("Failed to load probe database"));
+    let timing_preset = match matches.get_one::<String>("timing") {
+        Some(template) => {
+            let template: u8 = template.parse().expect("Invalid timing template (expected 0-5)");
+            timing::preset_for_template(template)
+        }
+        None => timing::fixed_preset(num_threads, timeout_ms),
+    };
+    let redis_target = matches
+        .get_one::<String>("redis")
+        .map(|url| (url.clone(), matches.get_one::<String>("redis-key").unwrap().clone()));
+
+    let (result_senders, writer_handles) = output::spawn_writers(format == "ndjson", redis_target);
 
     let start_time = Instant::now();
-    let results = scan_ports(target, start_port, end_port, num_threads, timeout_ms).await;
+    let results = scan_ports(target, start_port, end_port, timing_preset, tls_ports, probe_db, result_senders).await;
     let elapsed = start_time.elapsed();
     let total_ports = end_port - start_port + 1;
 
+    // ndjson is meant to be piped straight into line-oriented tooling, so
+    // these summaries (which aren't result lines) go to stderr instead of
+    // racing the ndjson writer task for stdout.
+    if format == "ndjson" {
+        eprintln!("Scanned {} ports per target.", total_ports);
+    } else {
+        println!("Scanned {} ports per target.", total_ports);
+    }
+
+    for handle in writer_handles {
+        let _ = handle.await;
+    }
+
     print_results(&results, format);
-    println!("Scanned {} ports in {:.2} seconds!", total_ports, elapsed.as_secs_f64());
+    if format == "ndjson" {
+        eprintln!("Scanned {} ports in {:.2} seconds!", total_ports, elapsed.as_secs_f64());
+    } else {
+        println!("Scanned {} ports in {:.2} seconds!", total_ports, elapsed.as_secs_f64());
+    }
 }
\ No newline at end of file
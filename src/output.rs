@@ -0,0 +1,75 @@
+//! Incremental result emission: as each open port is discovered during the
+//! scan it is pushed onto an output channel immediately, instead of waiting
+//! for the whole scan to finish. Two independent sinks can be enabled at
+//! once: newline-delimited JSON on stdout (`--format ndjson`) and RPUSH to
+//! a Redis list (`--redis`/`--redis-key`).
+
+use crate::ScanResult;
+use redis::AsyncCommands;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Spawns one background task per enabled sink, each draining its own
+/// channel so a slow broker never blocks the scan itself. Returns the
+/// senders the scan loop should push discovered results to, and the join
+/// handles to await once scanning finishes so the sinks can flush.
+pub fn spawn_writers(
+    ndjson: bool,
+    redis_target: Option<(String, String)>,
+) -> (Vec<mpsc::UnboundedSender<ScanResult>>, Vec<JoinHandle<()>>) {
+    let mut senders = Vec::new();
+    let mut handles = Vec::new();
+
+    if ndjson {
+        let (tx, rx) = mpsc::unbounded_channel();
+        senders.push(tx);
+        handles.push(tokio::spawn(ndjson_writer(rx)));
+    }
+
+    if let Some((url, key)) = redis_target {
+        let (tx, rx) = mpsc::unbounded_channel();
+        senders.push(tx);
+        handles.push(tokio::spawn(redis_writer(rx, url, key)));
+    }
+
+    (senders, handles)
+}
+
+async fn ndjson_writer(mut rx: mpsc::UnboundedReceiver<ScanResult>) {
+    while let Some(result) = rx.recv().await {
+        match serde_json::to_string(&result) {
+            Ok(line) => println!("{}", line),
+            Err(e) => eprintln!("Warning: failed to serialize result as ndjson: {}", e),
+        }
+    }
+}
+
+async fn redis_writer(mut rx: mpsc::UnboundedReceiver<ScanResult>, url: String, key: String) {
+    let client = match redis::Client::open(url.as_str()) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Warning: invalid --redis URL '{}': {}", url, e);
+            return;
+        }
+    };
+    let mut conn = match client.get_multiplexed_async_connection().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Warning: failed to connect to Redis at '{}': {}", url, e);
+            return;
+        }
+    };
+
+    while let Some(result) = rx.recv().await {
+        let payload = match serde_json::to_string(&result) {
+            Ok(payload) => payload,
+            Err(e) => {
+                eprintln!("Warning: failed to serialize result for Redis: {}", e);
+                continue;
+            }
+        };
+        if let Err(e) = conn.rpush::<_, _, ()>(&key, payload).await {
+            eprintln!("Warning: failed to RPUSH result to Redis: {}", e);
+        }
+    }
+}
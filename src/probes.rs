@@ -0,0 +1,391 @@
+//! nmap `service-probes`-style version-detection engine.
+//!
+//! Loads a probe database (`--probe-db`) describing what bytes to send on a
+//! socket and which regexes identify a service from the response, so new
+//! protocols can be added by editing a text file instead of recompiling.
+//!
+//! Supported directives: `Probe`, `ports`, `sslports`, `rarity`,
+//! `totalwaitms`, `match`, and `softmatch`. This is a practical subset of
+//! nmap's grammar, not a full reimplementation.
+
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Proto {
+    Tcp,
+    Udp,
+}
+
+/// A parsed `ports`/`sslports` list, e.g. `21-25,80,110,143,443`.
+#[derive(Debug, Clone, Default)]
+struct PortRanges(Vec<(u16, u16)>);
+
+impl PortRanges {
+    fn parse(spec: &str) -> Self {
+        let mut ranges = Vec::new();
+        for part in spec.split(',') {
+            let part = part.trim();
+            if part.is_empty() {
+                continue;
+            }
+            if let Some((lo, hi)) = part.split_once('-') {
+                if let (Ok(lo), Ok(hi)) = (lo.trim().parse(), hi.trim().parse()) {
+                    ranges.push((lo, hi));
+                }
+            } else if let Ok(p) = part.parse() {
+                ranges.push((p, p));
+            }
+        }
+        PortRanges(ranges)
+    }
+
+    fn contains(&self, port: u16) -> bool {
+        self.0.iter().any(|&(lo, hi)| port >= lo && port <= hi)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchRule {
+    pub service: String,
+    pub regex: Regex,
+    pub soft: bool,
+    product_tpl: Option<String>,
+    version_tpl: Option<String>,
+    info_tpl: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Probe {
+    pub proto: Proto,
+    pub name: String,
+    pub probe_string: Vec<u8>,
+    ports: PortRanges,
+    ssl_ports: PortRanges,
+    pub rarity: u32,
+    pub total_wait_ms: Option<u64>,
+    pub matches: Vec<MatchRule>,
+}
+
+impl Probe {
+    fn applies_to(&self, port: u16, is_tls: bool) -> bool {
+        if is_tls && !self.ssl_ports.is_empty() {
+            return self.ssl_ports.contains(port);
+        }
+        self.ports.is_empty() || self.ports.contains(port)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ProbeDb {
+    probes: Vec<Probe>,
+}
+
+/// Result produced by running the candidate probes against an open socket.
+#[derive(Debug, Clone)]
+pub struct Detection {
+    pub service: String,
+    pub product: Option<String>,
+    pub version: Option<String>,
+    pub info: Option<String>,
+}
+
+fn unescape_probe_string(raw: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('r') => out.push(b'\r'),
+            Some('n') => out.push(b'\n'),
+            Some('0') => out.push(0),
+            Some('t') => out.push(b'\t'),
+            Some('\\') => out.push(b'\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                    out.push(byte);
+                }
+            }
+            Some(other) => out.push(other as u8),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Splits a `q|...|` / `m|...|flags` delimited field: `tag` is the
+/// single-character field tag (`q` or `m`), which must be stripped before
+/// the delimiter itself can be read, as nmap allows any delimiter character
+/// right after the tag.
+fn split_delimited(rest: &str, tag: char) -> Option<(String, &str)> {
+    let rest = rest.strip_prefix(tag)?;
+    let mut chars = rest.char_indices();
+    let (_, delim) = chars.next()?;
+    let start = delim.len_utf8();
+    let tail = &rest[start..];
+    let end = tail.find(delim)?;
+    let body = tail[..end].to_string();
+    let after = &tail[end + delim.len_utf8()..];
+    Some((body, after))
+}
+
+fn parse_match_line(line: &str, soft: bool) -> Option<MatchRule> {
+    let line = if soft {
+        line.strip_prefix("softmatch ")?
+    } else {
+        line.strip_prefix("match ")?
+    };
+    let line = line.trim();
+    let (service, rest) = line.split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    let (pattern, rest) = split_delimited(rest, 'm')?;
+
+    let mut rest = rest;
+    let mut case_insensitive = false;
+    while let Some(flag) = rest.chars().next() {
+        match flag {
+            'i' => {
+                case_insensitive = true;
+                rest = &rest[1..];
+            }
+            's' => rest = &rest[1..],
+            _ => break,
+        }
+    }
+
+    let regex_src = if case_insensitive {
+        format!("(?i){}", pattern)
+    } else {
+        pattern
+    };
+    let regex = Regex::new(&regex_src).ok()?;
+
+    let mut product_tpl = None;
+    let mut version_tpl = None;
+    let mut info_tpl = None;
+    for field in rest.split_whitespace() {
+        if let Some(v) = field.strip_prefix("p/") {
+            product_tpl = Some(v.trim_end_matches('/').to_string());
+        } else if let Some(v) = field.strip_prefix("v/") {
+            version_tpl = Some(v.trim_end_matches('/').to_string());
+        } else if let Some(v) = field.strip_prefix("i/") {
+            info_tpl = Some(v.trim_end_matches('/').to_string());
+        }
+    }
+
+    Some(MatchRule {
+        service: service.to_string(),
+        regex,
+        soft,
+        product_tpl,
+        version_tpl,
+        info_tpl,
+    })
+}
+
+impl ProbeDb {
+    /// Parses a `service-probes`-style file into a probe database.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<ProbeDb> {
+        let contents = fs::read_to_string(path)?;
+        let mut probes = Vec::new();
+        let mut current: Option<Probe> = None;
+
+        for raw_line in contents.lines() {
+            let line = raw_line.trim_end();
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+
+            if let Some(rest) = line.strip_prefix("Probe ") {
+                if let Some(probe) = current.take() {
+                    probes.push(probe);
+                }
+                let mut parts = rest.splitn(2, char::is_whitespace);
+                let proto = match parts.next().unwrap_or("TCP") {
+                    "UDP" => Proto::Udp,
+                    _ => Proto::Tcp,
+                };
+                let rest = parts.next().unwrap_or("").trim_start();
+                let (name, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                let probe_string = split_delimited(rest.trim_start(), 'q')
+                    .map(|(body, _)| unescape_probe_string(&body))
+                    .unwrap_or_default();
+
+                current = Some(Probe {
+                    proto,
+                    name: name.to_string(),
+                    probe_string,
+                    ports: PortRanges::default(),
+                    ssl_ports: PortRanges::default(),
+                    rarity: 0,
+                    total_wait_ms: None,
+                    matches: Vec::new(),
+                });
+                continue;
+            }
+
+            let Some(probe) = current.as_mut() else {
+                continue;
+            };
+
+            if let Some(rest) = line.strip_prefix("ports ") {
+                probe.ports = PortRanges::parse(rest.trim());
+            } else if let Some(rest) = line.strip_prefix("sslports ") {
+                probe.ssl_ports = PortRanges::parse(rest.trim());
+            } else if let Some(rest) = line.strip_prefix("rarity ") {
+                probe.rarity = rest.trim().parse().unwrap_or(0);
+            } else if let Some(rest) = line.strip_prefix("totalwaitms ") {
+                probe.total_wait_ms = rest.trim().parse().ok();
+            } else if line.starts_with("match ") {
+                if let Some(m) = parse_match_line(line, false) {
+                    probe.matches.push(m);
+                }
+            } else if line.starts_with("softmatch ") {
+                if let Some(m) = parse_match_line(line, true) {
+                    probe.matches.push(m);
+                }
+            }
+        }
+        if let Some(probe) = current.take() {
+            probes.push(probe);
+        }
+
+        Ok(ProbeDb { probes })
+    }
+
+    /// Candidate probes for `port`, the NULL probe first (as nmap does),
+    /// then the rest ordered by port-applicability and ascending rarity.
+    ///
+    /// A probe is a candidate if its `ports`/`sslports` list covers `port`,
+    /// or if its rarity is low enough (at most the intensity) to try against
+    /// any port (nmap's
+    /// `--version-intensity`, defaulted here since RPoke doesn't expose the
+    /// flag); this keeps a large probe-db from reconnecting hundreds of
+    /// times against a single slow/filtered port.
+    fn candidates(&self, port: u16, is_tls: bool) -> Vec<&Probe> {
+        const DEFAULT_INTENSITY: u32 = 7;
+
+        let mut candidates: Vec<&Probe> = self
+            .probes
+            .iter()
+            .filter(|p| p.proto == Proto::Tcp)
+            .filter(|p| {
+                p.name == "NULL" || p.applies_to(port, is_tls) || p.rarity <= DEFAULT_INTENSITY
+            })
+            .collect();
+
+        candidates.sort_by_key(|p| {
+            let is_null = p.name == "NULL";
+            let applies = p.applies_to(port, is_tls);
+            (!is_null, !applies, p.rarity)
+        });
+
+        candidates
+    }
+
+    /// Calls `probe_io` for each candidate probe in turn (it sends the probe
+    /// string and returns the response) and tries the probe's match rules on
+    /// that response, until a hard match fires or the candidates are exhausted.
+    pub async fn detect<F, Fut>(&self, port: u16, is_tls: bool, mut probe_io: F) -> Option<Detection>
+    where
+        F: FnMut(Vec<u8>, Option<u64>) -> Fut,
+        Fut: std::future::Future<Output = Vec<u8>>,
+    {
+        let mut soft_result: Option<Detection> = None;
+
+        for probe in self.candidates(port, is_tls) {
+            let response = probe_io(probe.probe_string.clone(), probe.total_wait_ms).await;
+            if response.is_empty() && !probe.probe_string.is_empty() {
+                continue;
+            }
+            let response = String::from_utf8_lossy(&response);
+
+            for rule in &probe.matches {
+                let Some(caps) = rule.regex.captures(&response) else {
+                    continue;
+                };
+                let expand = |tpl: &Option<String>| -> Option<String> {
+                    tpl.as_ref().map(|t| {
+                        let mut out = t.clone();
+                        for i in 1..caps.len() {
+                            if let Some(group) = caps.get(i) {
+                                out = out.replace(&format!("${}", i), group.as_str());
+                            }
+                        }
+                        out
+                    })
+                };
+
+                let detection = Detection {
+                    service: rule.service.clone(),
+                    product: expand(&rule.product_tpl),
+                    version: expand(&rule.version_tpl),
+                    info: expand(&rule.info_tpl),
+                };
+
+                if rule.soft {
+                    soft_result = Some(detection);
+                } else {
+                    return Some(detection);
+                }
+            }
+        }
+
+        soft_result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_probe_string_handles_escapes() {
+        assert_eq!(unescape_probe_string(r"\r\n\0\t\\"), b"\r\n\0\t\\");
+        assert_eq!(unescape_probe_string(r"\x41\x42"), b"AB");
+        assert_eq!(unescape_probe_string("plain"), b"plain");
+    }
+
+    #[test]
+    fn split_delimited_strips_tag_before_delimiter() {
+        let (body, after) = split_delimited(r"q|\r\n\r\n|", 'q').unwrap();
+        assert_eq!(body, r"\r\n\r\n");
+        assert_eq!(after, "");
+
+        let (body, after) = split_delimited(r"m|^SSH-(\d)| i/banner/", 'm').unwrap();
+        assert_eq!(body, r"^SSH-(\d)");
+        assert_eq!(after, " i/banner/");
+    }
+
+    #[test]
+    fn split_delimited_rejects_wrong_tag() {
+        assert!(split_delimited(r"q|\r\n|", 'm').is_none());
+    }
+
+    #[test]
+    fn parse_match_line_extracts_regex_and_templates() {
+        let rule = parse_match_line(r"match test-echo m|^HELLO-(\d+)\r\n| p/TestSvc/ v/$1/", false).unwrap();
+        assert_eq!(rule.service, "test-echo");
+        assert!(!rule.soft);
+        assert!(rule.regex.is_match("HELLO-42\r\n"));
+        assert_eq!(rule.product_tpl.as_deref(), Some("TestSvc"));
+        assert_eq!(rule.version_tpl.as_deref(), Some("$1"));
+    }
+
+    #[test]
+    fn parse_match_line_handles_softmatch_and_case_insensitive_flag() {
+        let rule = parse_match_line(r"softmatch generic m|^ok$|i", true).unwrap();
+        assert!(rule.soft);
+        assert!(rule.regex.is_match("OK"));
+    }
+}
@@ -0,0 +1,102 @@
+//! Target parsing and expansion: turns the raw `-t/--target` string into a
+//! lazy stream of resolved targets so that large CIDR blocks don't have to
+//! be materialized into a `Vec` up front.
+
+use futures::stream::{self, Stream, StreamExt};
+use hickory_resolver::config::{LookupIpStrategy, ResolverConfig, ResolverOpts};
+use hickory_resolver::TokioAsyncResolver;
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+/// One comma-separated entry from `-t/--target`, before expansion.
+#[derive(Debug, Clone)]
+enum TargetSpec {
+    Addr(IpAddr),
+    Cidr(IpNet),
+    Host(String),
+    Unix(String),
+}
+
+/// A single target produced by expansion: either a network address to be
+/// crossed with the port range, or a Unix domain socket path scanned once.
+#[derive(Debug, Clone)]
+pub enum ResolvedTarget {
+    /// `hostname` is the original name this address was resolved from, kept
+    /// around so TLS probing can send it as SNI instead of an IP literal;
+    /// it's `None` for bare IP/CIDR targets, which have no hostname to send.
+    Net { ip: IpAddr, hostname: Option<String> },
+    Unix(String),
+}
+
+fn parse_spec(raw: &str) -> TargetSpec {
+    let raw = raw.trim();
+    if let Some(path) = raw.strip_prefix("unix:") {
+        return TargetSpec::Unix(path.to_string());
+    }
+    if let Ok(net) = raw.parse::<IpNet>() {
+        return TargetSpec::Cidr(net);
+    }
+    if let Ok(addr) = raw.parse::<IpAddr>() {
+        return TargetSpec::Addr(addr);
+    }
+    TargetSpec::Host(raw.to_string())
+}
+
+/// Parses the comma-separated `--target` value into individual specs.
+fn parse_targets(raw: &str) -> Vec<TargetSpec> {
+    raw.split(',').map(parse_spec).collect()
+}
+
+/// Resolves a hostname to both its A and AAAA records.
+async fn resolve_host(resolver: &TokioAsyncResolver, host: &str) -> Vec<IpAddr> {
+    match resolver.lookup_ip(host).await {
+        Ok(lookup) => lookup.iter().collect(),
+        Err(e) => {
+            eprintln!("Warning: failed to resolve '{}': {}", host, e);
+            Vec::new()
+        }
+    }
+}
+
+/// Expands the `--target` value into a lazily-produced stream of resolved
+/// targets.
+///
+/// CIDR blocks are expanded via `IpNet::hosts()`, which computes each address
+/// on demand rather than collecting the whole range, so a `/8` doesn't blow
+/// up memory before scanning even starts. `unix:/path` entries pass straight
+/// through as a single `ResolvedTarget::Unix`.
+pub async fn expand_targets(raw: &str) -> impl Stream<Item = ResolvedTarget> {
+    let mut opts = ResolverOpts::default();
+    opts.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+    let resolver = TokioAsyncResolver::tokio(ResolverConfig::default(), opts);
+    let specs = parse_targets(raw);
+
+    stream::iter(specs)
+        .then(move |spec| {
+            let resolver = resolver.clone();
+            async move {
+                match spec {
+                    TargetSpec::Addr(ip) => {
+                        stream::iter(vec![ResolvedTarget::Net { ip, hostname: None }]).boxed()
+                    }
+                    TargetSpec::Cidr(net) => stream::iter(
+                        net.hosts()
+                            .map(|ip| ResolvedTarget::Net { ip, hostname: None }),
+                    )
+                    .boxed(),
+                    TargetSpec::Host(host) => stream::iter(
+                        resolve_host(&resolver, &host)
+                            .await
+                            .into_iter()
+                            .map(move |ip| ResolvedTarget::Net {
+                                ip,
+                                hostname: Some(host.clone()),
+                            }),
+                    )
+                    .boxed(),
+                    TargetSpec::Unix(path) => stream::iter(vec![ResolvedTarget::Unix(path)]).boxed(),
+                }
+            }
+        })
+        .flatten()
+}
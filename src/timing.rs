@@ -0,0 +1,196 @@
+//! nmap-style timing templates (`--timing 0`..`--timing 5`) and an adaptive controller
+//! that resizes the in-flight scan window and per-probe timeout from the
+//! observed RTT and failure ratio, instead of a single static value.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// How many of the most recent results are considered when computing the
+/// failure ratio that drives window shrink/grow decisions.
+const FAILURE_WINDOW: usize = 20;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TimingPreset {
+    pub min_window: usize,
+    pub max_window: usize,
+    pub initial_timeout_ms: u64,
+    pub min_timeout_ms: u64,
+    pub max_timeout_ms: u64,
+    pub failure_threshold: f64,
+    pub shrink_factor: f64,
+    pub grow_step: usize,
+}
+
+/// Reproduces the old fixed-concurrency/fixed-timeout behavior as a preset,
+/// for when the user passes explicit `--threads`/`--timeout` instead of a
+/// `--timing` template: the window never shrinks or grows.
+pub fn fixed_preset(threads: usize, timeout_ms: u64) -> TimingPreset {
+    let threads = threads.max(1);
+    TimingPreset {
+        min_window: threads,
+        max_window: threads,
+        initial_timeout_ms: timeout_ms,
+        min_timeout_ms: timeout_ms,
+        max_timeout_ms: timeout_ms,
+        failure_threshold: 1.1,
+        shrink_factor: 1.0,
+        grow_step: 0,
+    }
+}
+
+/// Presets loosely modeled on nmap's `-T0` (paranoid) through `-T5`
+/// (insane): each step trades reliability for speed by widening the
+/// concurrency ceiling and tightening timeouts.
+pub fn preset_for_template(template: u8) -> TimingPreset {
+    match template {
+        0 => TimingPreset {
+            min_window: 1,
+            max_window: 4,
+            initial_timeout_ms: 5000,
+            min_timeout_ms: 2000,
+            max_timeout_ms: 10000,
+            failure_threshold: 0.1,
+            shrink_factor: 0.5,
+            grow_step: 1,
+        },
+        1 => TimingPreset {
+            min_window: 1,
+            max_window: 16,
+            initial_timeout_ms: 3000,
+            min_timeout_ms: 1000,
+            max_timeout_ms: 8000,
+            failure_threshold: 0.15,
+            shrink_factor: 0.5,
+            grow_step: 2,
+        },
+        2 => TimingPreset {
+            min_window: 4,
+            max_window: 64,
+            initial_timeout_ms: 1500,
+            min_timeout_ms: 500,
+            max_timeout_ms: 5000,
+            failure_threshold: 0.2,
+            shrink_factor: 0.6,
+            grow_step: 4,
+        },
+        3 => TimingPreset {
+            min_window: 8,
+            max_window: 256,
+            initial_timeout_ms: 1000,
+            min_timeout_ms: 250,
+            max_timeout_ms: 3000,
+            failure_threshold: 0.25,
+            shrink_factor: 0.6,
+            grow_step: 8,
+        },
+        4 => TimingPreset {
+            min_window: 16,
+            max_window: 1024,
+            initial_timeout_ms: 750,
+            min_timeout_ms: 150,
+            max_timeout_ms: 2000,
+            failure_threshold: 0.3,
+            shrink_factor: 0.7,
+            grow_step: 32,
+        },
+        _ => TimingPreset {
+            min_window: 32,
+            max_window: 4096,
+            initial_timeout_ms: 500,
+            min_timeout_ms: 75,
+            max_timeout_ms: 1250,
+            failure_threshold: 0.4,
+            shrink_factor: 0.75,
+            grow_step: 128,
+        },
+    }
+}
+
+/// Tracks RTT/loss from completed probes and uses them to resize the
+/// in-flight window and per-probe timeout between the preset's min/max.
+pub struct AdaptiveController {
+    preset: TimingPreset,
+    semaphore: std::sync::Arc<Semaphore>,
+    window: AtomicUsize,
+    srtt_ms: Mutex<f64>,
+    rttvar_ms: Mutex<f64>,
+    recent_results: Mutex<VecDeque<bool>>,
+}
+
+impl AdaptiveController {
+    pub fn new(preset: TimingPreset) -> std::sync::Arc<Self> {
+        std::sync::Arc::new(AdaptiveController {
+            semaphore: std::sync::Arc::new(Semaphore::new(preset.max_window)),
+            window: AtomicUsize::new(preset.min_window.max(1)),
+            srtt_ms: Mutex::new(preset.initial_timeout_ms as f64 / 2.0),
+            rttvar_ms: Mutex::new(preset.initial_timeout_ms as f64 / 4.0),
+            recent_results: Mutex::new(VecDeque::with_capacity(FAILURE_WINDOW)),
+            preset,
+        })
+    }
+
+    /// Blocks until a slot in the current window is free, then holds it for
+    /// the duration of one scan attempt.
+    pub async fn acquire(self: &std::sync::Arc<Self>) -> OwnedSemaphorePermit {
+        loop {
+            let window = self.window.load(Ordering::Relaxed);
+            let in_flight = self.preset.max_window - self.semaphore.available_permits();
+            if in_flight < window {
+                if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
+                    return permit;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    /// Current per-probe timeout, derived as `srtt + 4*rttvar` à la TCP,
+    /// clamped to the preset's bounds.
+    pub fn current_timeout_ms(&self) -> u64 {
+        let srtt = *self.srtt_ms.lock().unwrap();
+        let rttvar = *self.rttvar_ms.lock().unwrap();
+        let timeout = srtt + 4.0 * rttvar;
+        (timeout as u64).clamp(self.preset.min_timeout_ms, self.preset.max_timeout_ms)
+    }
+
+    /// Feeds a completed scan attempt's outcome back into the controller,
+    /// updating the RTT estimate (on success) and resizing the window.
+    pub fn record(&self, success: bool, rtt: Option<Duration>) {
+        if let Some(rtt) = rtt {
+            let sample = rtt.as_secs_f64() * 1000.0;
+            let mut srtt = self.srtt_ms.lock().unwrap();
+            let mut rttvar = self.rttvar_ms.lock().unwrap();
+            const ALPHA: f64 = 0.125;
+            const BETA: f64 = 0.25;
+            *rttvar = (1.0 - BETA) * *rttvar + BETA * (*srtt - sample).abs();
+            *srtt = (1.0 - ALPHA) * *srtt + ALPHA * sample;
+        }
+
+        let failure_ratio = {
+            let mut recent = self.recent_results.lock().unwrap();
+            if recent.len() == FAILURE_WINDOW {
+                recent.pop_front();
+            }
+            recent.push_back(success);
+            let failures = recent.iter().filter(|&&ok| !ok).count();
+            failures as f64 / recent.len() as f64
+        };
+
+        if failure_ratio > self.preset.failure_threshold {
+            self.window
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |w| {
+                    Some(((w as f64 * self.preset.shrink_factor) as usize).max(self.preset.min_window))
+                })
+                .ok();
+        } else {
+            self.window
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |w| {
+                    Some((w + self.preset.grow_step).min(self.preset.max_window))
+                })
+                .ok();
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Real TLS handshake probing for ports that speak SSL/TLS.
+//!
+//! Certificate verification is intentionally disabled: RPoke is scanning
+//! arbitrary hosts it has no trust anchor for, so the goal is to capture
+//! whatever the peer presents, not to validate it.
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::{client::TlsStream, TlsConnector};
+use x509_parser::prelude::*;
+
+/// Ports treated as TLS by default, on top of anything passed via `--tls-ports`.
+pub const DEFAULT_TLS_PORTS: &[u16] = &[443, 8443, 465, 993, 995];
+
+pub fn is_tls_port(port: u16, extra_ports: &[u16]) -> bool {
+    DEFAULT_TLS_PORTS.contains(&port) || extra_ports.contains(&port)
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TlsInfo {
+    pub protocol: String,
+    pub cipher_suite: String,
+    pub alpn: Option<String>,
+    pub subject_cn: Option<String>,
+    pub sans: Vec<String>,
+    pub issuer: Option<String>,
+    pub not_before: String,
+    pub not_after: String,
+}
+
+#[derive(Debug)]
+struct NoVerification;
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        vec![
+            SignatureScheme::RSA_PKCS1_SHA1,
+            SignatureScheme::ECDSA_SHA1_Legacy,
+            SignatureScheme::RSA_PKCS1_SHA256,
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+            SignatureScheme::RSA_PKCS1_SHA384,
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            SignatureScheme::RSA_PKCS1_SHA512,
+            SignatureScheme::ECDSA_NISTP521_SHA512,
+            SignatureScheme::RSA_PSS_SHA256,
+            SignatureScheme::RSA_PSS_SHA384,
+            SignatureScheme::RSA_PSS_SHA512,
+            SignatureScheme::ED25519,
+        ]
+    }
+}
+
+fn client_config() -> Arc<ClientConfig> {
+    let mut config = ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(NoVerification))
+        .with_no_client_auth();
+    config.alpn_protocols = vec![b"http/1.1".to_vec(), b"h2".to_vec()];
+    Arc::new(config)
+}
+
+fn rfc2822(asn1_time: &x509_parser::time::ASN1Time) -> String {
+    asn1_time.to_rfc2822().unwrap_or_else(|_| asn1_time.to_string())
+}
+
+fn parse_leaf_certificate(der: &CertificateDer<'_>) -> (Option<String>, Vec<String>, Option<String>, String, String) {
+    match X509Certificate::from_der(der.as_ref()) {
+        Ok((_, cert)) => {
+            let subject_cn = cert
+                .subject()
+                .iter_common_name()
+                .next()
+                .and_then(|cn| cn.as_str().ok())
+                .map(|s| s.to_string());
+            let issuer = cert
+                .issuer()
+                .iter_common_name()
+                .next()
+                .and_then(|cn| cn.as_str().ok())
+                .map(|s| s.to_string());
+            let sans = cert
+                .subject_alternative_name()
+                .ok()
+                .flatten()
+                .map(|ext| {
+                    ext.value
+                        .general_names
+                        .iter()
+                        .map(|name| name.to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+            let not_before = rfc2822(&cert.validity().not_before);
+            let not_after = rfc2822(&cert.validity().not_after);
+            (subject_cn, sans, issuer, not_before, not_after)
+        }
+        Err(_) => (None, Vec::new(), None, String::new(), String::new()),
+    }
+}
+
+/// Performs a TLS handshake over the connected `stream`, then hands the decrypted stream
+/// back along with the negotiated session metadata and leaf certificate
+/// fields so the caller can keep running its normal application-layer probe.
+pub async fn handshake(
+    stream: TcpStream,
+    sni: &str,
+    timeout_ms: u64,
+) -> Option<(TlsStream<TcpStream>, TlsInfo)> {
+    let connector = TlsConnector::from(client_config());
+    let server_name = ServerName::try_from(sni.to_string()).ok()?;
+
+    let tls_stream = timeout(
+        Duration::from_millis(timeout_ms),
+        connector.connect(server_name, stream),
+    )
+    .await
+    .ok()?
+    .ok()?;
+
+    let (_, session) = tls_stream.get_ref();
+
+    let protocol = session
+        .protocol_version()
+        .map(|v| format!("{:?}", v))
+        .unwrap_or_else(|| "Unknown".to_string());
+    let cipher_suite = session
+        .negotiated_cipher_suite()
+        .map(|cs| format!("{:?}", cs.suite()))
+        .unwrap_or_else(|| "Unknown".to_string());
+    let alpn = session
+        .alpn_protocol()
+        .map(|p| String::from_utf8_lossy(p).to_string());
+
+    let (subject_cn, sans, issuer, not_before, not_after) = session
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+        .map(parse_leaf_certificate)
+        .unwrap_or((None, Vec::new(), None, String::new(), String::new()));
+
+    let info = TlsInfo {
+        protocol,
+        cipher_suite,
+        alpn,
+        subject_cn,
+        sans,
+        issuer,
+        not_before,
+        not_after,
+    };
+
+    Some((tls_stream, info))
+}
+
+/// Writes `probe` and reads a response over an already-established TLS
+/// stream, mirroring the plaintext banner-grab path.
+pub async fn probe_banner(stream: &mut TlsStream<TcpStream>, probe: &[u8]) -> String {
+    let _ = stream.write_all(probe).await;
+    let mut buffer = [0u8; 1024];
+    let n = stream.read(&mut buffer).await.unwrap_or(0);
+    String::from_utf8_lossy(&buffer[..n]).to_string()
+}
@@ -0,0 +1,31 @@
+//! Unix domain socket probing, built on top of the target-expansion
+//! pipeline: a `unix:/path` target connects and reuses the same
+//! service/banner detection logic as TCP ports, just without a port number.
+
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::time::timeout;
+
+/// Connects to `path`, optionally writes `probe_bytes`, and reads back
+/// whatever the socket sends. Returns `None` if the socket couldn't be
+/// connected to at all; `Some("")` means it connected but sent nothing.
+pub async fn probe(path: &str, timeout_ms: u64, probe_bytes: &[u8]) -> Option<String> {
+    let timeout_duration = Duration::from_millis(timeout_ms);
+    let mut stream = timeout(timeout_duration, UnixStream::connect(path))
+        .await
+        .ok()?
+        .ok()?;
+
+    if !probe_bytes.is_empty() {
+        let _ = stream.write_all(probe_bytes).await;
+    }
+    let mut buffer = [0u8; 1024];
+    let n = timeout(timeout_duration, stream.read(&mut buffer))
+        .await
+        .ok()
+        .and_then(|r| r.ok())
+        .unwrap_or(0);
+
+    Some(String::from_utf8_lossy(&buffer[..n]).to_string())
+}